@@ -1,8 +1,31 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
 use leptos::prelude::*;
+use wasm_bindgen::prelude::*;
 use wgpu::util::DeviceExt;
 use wasm_bindgen_futures::spawn_local;
 
-use crate::vertex::{Vertex, VERTICES};
+use crate::filter::{FilterChain, DEFAULT_PRESET};
+use crate::hud::{HudHandle, HudOverlay, TextSection};
+use crate::texture::Texture;
+use crate::vertex::{Vertex, INDICES, VERTICES};
+
+const PLACEHOLDER_TEXTURE_BYTES: &[u8] = include_bytes!("../assets/placeholder.png");
+
+/// MSAA sample count used for the offscreen render target. 4x is the
+/// common sweet spot between edge smoothing and GPU memory/bandwidth cost.
+const SAMPLE_COUNT: u32 = 4;
+
+/// Per-frame uniform data shared with the shader: elapsed time in seconds and
+/// the current surface resolution in physical pixels.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    time: f32,
+    _padding: f32,
+    resolution: [f32; 2],
+}
 
 async fn init_wgpu(canvas: web_sys::HtmlCanvasElement) -> Result<(wgpu::Device, wgpu::Surface<'static>, wgpu::Queue, wgpu::SurfaceConfiguration), Box<dyn std::error::Error>> {
     let instance_desc = wgpu::InstanceDescriptor {
@@ -53,7 +76,28 @@ async fn init_wgpu(canvas: web_sys::HtmlCanvasElement) -> Result<(wgpu::Device,
     Ok((device, surface, queue, config))
 }
 
-async fn create_pipeline(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::RenderPipeline {
+fn create_uniform_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Uniform Bind Group Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+async fn create_pipeline(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    uniform_bind_group_layout: &wgpu::BindGroupLayout,
+    texture_bind_group_layout: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Shader"),
         source: wgpu::ShaderSource::Wgsl(include_str!("../shader.wgsl").into()),
@@ -61,7 +105,7 @@ async fn create_pipeline(device: &wgpu::Device, config: &wgpu::SurfaceConfigurat
 
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Render Pipeline Layout"),
-        bind_group_layouts: &[],
+        bind_group_layouts: &[uniform_bind_group_layout, texture_bind_group_layout],
         push_constant_ranges: &[],
     });
 
@@ -87,6 +131,12 @@ async fn create_pipeline(device: &wgpu::Device, config: &wgpu::SurfaceConfigurat
                         offset: std::mem::size_of::<[f32; 3]>() as u64,
                         shader_location: 1,
                     },
+                    // UV
+                    wgpu::VertexAttribute {
+                        format: wgpu::VertexFormat::Float32x2,
+                        offset: std::mem::size_of::<[f32; 3]>() as u64 * 2,
+                        shader_location: 2,
+                    },
                 ],
             }],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
@@ -112,7 +162,7 @@ async fn create_pipeline(device: &wgpu::Device, config: &wgpu::SurfaceConfigurat
         },
         depth_stencil: None,
         multisample: wgpu::MultisampleState {
-            count: 1,
+            count: SAMPLE_COUNT,
             mask: !0,
             alpha_to_coverage_enabled: false,
         },
@@ -121,71 +171,345 @@ async fn create_pipeline(device: &wgpu::Device, config: &wgpu::SurfaceConfigurat
     })
 }
 
+/// Creates the multisampled offscreen color target the scene renders into;
+/// it's resolved down to the single-sample scene target each frame.
+fn create_msaa_view(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("MSAA Framebuffer"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: SAMPLE_COUNT,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Creates the single-sample scene target the MSAA framebuffer resolves
+/// into. The filter chain samples this as its first pass's input, so it
+/// needs `TEXTURE_BINDING` in addition to `RENDER_ATTACHMENT`.
+fn create_scene_view(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Scene Target"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn now_seconds() -> f32 {
+    (web_sys::window()
+        .and_then(|w| w.performance())
+        .map(|p| p.now())
+        .unwrap_or(0.0)
+        / 1000.0) as f32
+}
+
+/// Resizes `surface` to match `canvas`'s CSS size (scaled by the device pixel
+/// ratio, for crisp HiDPI output) and keeps `config` in sync. Called once up
+/// front and again on every `ResizeObserver` callback.
+fn resize_to_canvas(
+    canvas: &web_sys::HtmlCanvasElement,
+    device: &wgpu::Device,
+    surface: &wgpu::Surface<'static>,
+    config: &Rc<RefCell<wgpu::SurfaceConfiguration>>,
+    msaa_view: &Rc<RefCell<wgpu::TextureView>>,
+    scene_view: &Rc<RefCell<wgpu::TextureView>>,
+    filter_chain: &Rc<RefCell<FilterChain>>,
+    configured: &Cell<bool>,
+) {
+    let dpr = web_sys::window().map(|w| w.device_pixel_ratio()).unwrap_or(1.0);
+    let rect = canvas.get_bounding_client_rect();
+    let width = ((rect.width() * dpr) as u32).max(1);
+    let height = ((rect.height() * dpr) as u32).max(1);
+
+    // Skipping same-size resizes is only safe once the surface has actually
+    // been configured at least once -- otherwise a canvas that already
+    // happens to match the browser's default 300x150 backing store on the
+    // very first call would never get `surface.configure`'d at all, and the
+    // first `get_current_texture` would panic.
+    if configured.get() && width == canvas.width() && height == canvas.height() {
+        return;
+    }
+
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let mut config = config.borrow_mut();
+    config.width = width;
+    config.height = height;
+    surface.configure(device, &config);
+    configured.set(true);
+    *msaa_view.borrow_mut() = create_msaa_view(device, &config);
+    *scene_view.borrow_mut() = create_scene_view(device, &config);
+    filter_chain.borrow_mut().resize(device, width, height);
+}
+
+/// Watches `canvas` for layout-size changes (window resizes, flex/grid
+/// reflows, ...) and keeps the swapchain's backing store (and the MSAA
+/// target sized to match it) so frames aren't stretched or
+/// `get_current_texture` doesn't error.
+fn watch_canvas_resize(
+    canvas: web_sys::HtmlCanvasElement,
+    device: wgpu::Device,
+    surface: Rc<wgpu::Surface<'static>>,
+    config: Rc<RefCell<wgpu::SurfaceConfiguration>>,
+    msaa_view: Rc<RefCell<wgpu::TextureView>>,
+    scene_view: Rc<RefCell<wgpu::TextureView>>,
+    filter_chain: Rc<RefCell<FilterChain>>,
+) {
+    let configured = Rc::new(Cell::new(false));
+
+    resize_to_canvas(&canvas, &device, &surface, &config, &msaa_view, &scene_view, &filter_chain, &configured);
+
+    let closure = Closure::<dyn FnMut(js_sys::Array)>::new(move |_entries: js_sys::Array| {
+        resize_to_canvas(&canvas, &device, &surface, &config, &msaa_view, &scene_view, &filter_chain, &configured);
+    });
+
+    let observer = web_sys::ResizeObserver::new(closure.as_ref().unchecked_ref())
+        .expect("failed to create ResizeObserver");
+    observer.observe(&canvas);
+
+    // The observer and its callback must outlive this function; there's no
+    // teardown path for this single-page app, so we leak them deliberately.
+    closure.forget();
+    Box::leak(Box::new(observer));
+}
+
 async fn render_loop(
     device: wgpu::Device,
     surface: wgpu::Surface<'static>,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
+    canvas: web_sys::HtmlCanvasElement,
+    hud_handle: HudHandle,
 ) {
     let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: None,
         contents: bytemuck::cast_slice(VERTICES),
         usage: wgpu::BufferUsages::VERTEX,
     });
+    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Index Buffer"),
+        contents: bytemuck::cast_slice(INDICES),
+        usage: wgpu::BufferUsages::INDEX,
+    });
+    let index_count = INDICES.len() as u32;
 
-    let pipeline = create_pipeline(&device, &config).await;
-
-    let render = {
-        let device = device.clone();
-        let queue = queue.clone();
-
-        move || {
-            let frame = surface.get_current_texture().unwrap();
-            let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-            {
-                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: None,
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                            store: wgpu::StoreOp::Store,
-                        },
-                    })],
-                    depth_stencil_attachment: None,
-                    timestamp_writes: None,
-                    occlusion_query_set: None,
-                });
-
-                render_pass.set_pipeline(&pipeline);
-                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-                render_pass.draw(0..3, 0..1);
-            }
+    let surface = Rc::new(surface);
+    let msaa_view = Rc::new(RefCell::new(create_msaa_view(&device, &config)));
+    let scene_view = Rc::new(RefCell::new(create_scene_view(&device, &config)));
+    let filter_chain = Rc::new(RefCell::new(
+        FilterChain::from_preset(&device, config.format, config.width, config.height, DEFAULT_PRESET)
+            .expect("default filter preset should parse"),
+    ));
+    let mut hud = HudOverlay::new(&device, &queue, config.format);
+    // The render loop's own FPS readout lives on a private handle so that
+    // clearing and re-queuing it every frame never clobbers text pushed
+    // onto the externally-shared `hud_handle`.
+    let fps_handle = HudHandle::default();
+    let config = Rc::new(RefCell::new(config));
+
+    watch_canvas_resize(
+        canvas,
+        device.clone(),
+        surface.clone(),
+        config.clone(),
+        msaa_view.clone(),
+        scene_view.clone(),
+        filter_chain.clone(),
+    );
+
+    let uniform_bind_group_layout = create_uniform_bind_group_layout(&device);
+    let texture_bind_group_layout = Texture::bind_group_layout(&device);
+    let pipeline = create_pipeline(
+        &device,
+        &config.borrow(),
+        &uniform_bind_group_layout,
+        &texture_bind_group_layout,
+    )
+    .await;
+
+    let texture = Texture::from_bytes(&device, &queue, PLACEHOLDER_TEXTURE_BYTES, "Placeholder Texture")
+        .expect("failed to decode placeholder texture");
+    let texture_bind_group = texture.bind_group(&device, &texture_bind_group_layout);
+
+    let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Uniform Buffer"),
+        contents: bytemuck::bytes_of(&Uniforms {
+            time: 0.0,
+            _padding: 0.0,
+            resolution: [config.borrow().width as f32, config.borrow().height as f32],
+        }),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Uniform Bind Group"),
+        layout: &uniform_bind_group_layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: uniform_buffer.as_entire_binding(),
+        }],
+    });
+
+    let start_time = now_seconds();
+    let last_frame_time = Cell::new(0.0f32);
+
+    let render = move |time: f32| {
+        let fps = 1.0 / (time - last_frame_time.replace(time)).max(1.0 / 1000.0);
+
+        let (width, height) = {
+            let config = config.borrow();
+            (config.width, config.height)
+        };
+
+        queue.write_buffer(
+            &uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                time,
+                _padding: 0.0,
+                resolution: [width as f32, height as f32],
+            }),
+        );
 
-            queue.submit(Some(encoder.finish()));
-            frame.present();
+        let frame = surface.get_current_texture().unwrap();
+        let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let msaa_view = msaa_view.borrow();
+        let scene_view = scene_view.borrow();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &msaa_view,
+                    resolve_target: Some(&scene_view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(&pipeline);
+            render_pass.set_bind_group(0, &uniform_bind_group, &[]);
+            render_pass.set_bind_group(1, &texture_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..index_count, 0, 0..1);
+        }
+
+        filter_chain
+            .borrow_mut()
+            .render(&device, &queue, &mut encoder, &scene_view, &view);
+
+        fps_handle.clear();
+        fps_handle.push(TextSection::new(format!("{fps:.0} FPS"), [12.0, 12.0]));
+        let sections: Vec<TextSection> = fps_handle
+            .sections()
+            .iter()
+            .chain(hud_handle.sections().iter())
+            .cloned()
+            .collect();
+        hud.prepare(&device, &queue, width, height, &sections);
+
+        {
+            let mut hud_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("HUD Overlay Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            hud.render(&mut hud_pass);
         }
+
+        queue.submit(Some(encoder.finish()));
+        frame.present();
     };
 
-    render();
+    // Drive `render` from a `requestAnimationFrame` loop. The closure needs to
+    // call itself, so it's stored in a `Rc<RefCell<Option<_>>>` and re-borrows
+    // its own slot to schedule the next frame.
+    let frame_closure: Rc<RefCell<Option<Closure<dyn FnMut()>>>> = Rc::new(RefCell::new(None));
+    let frame_closure_handle = frame_closure.clone();
+
+    *frame_closure_handle.borrow_mut() = Some(Closure::new(move || {
+        render(now_seconds() - start_time);
+
+        request_animation_frame(
+            frame_closure
+                .borrow()
+                .as_ref()
+                .unwrap()
+                .as_ref()
+                .unchecked_ref(),
+        );
+    }));
+
+    request_animation_frame(
+        frame_closure_handle
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .as_ref()
+            .unchecked_ref(),
+    );
+}
+
+fn request_animation_frame(callback: &js_sys::Function) {
+    web_sys::window()
+        .expect("no global `window` exists")
+        .request_animation_frame(callback)
+        .expect("should register `requestAnimationFrame`");
 }
 
 #[component]
 pub fn Canvas() -> impl IntoView {
     let canvas_ref = NodeRef::<leptos::html::Canvas>::new();
+    // Provided as context so sibling/child components can pull this handle
+    // via `use_context::<HudHandle>()` and queue their own HUD text
+    // alongside the live FPS readout the render loop already drives.
+    let hud_handle = HudHandle::default();
+    provide_context(hud_handle.clone());
 
     Effect::new(move |_| {
         let canvas = canvas_ref.get().unwrap();
+        let render_canvas = canvas.clone();
+        let hud_handle = hud_handle.clone();
 
         // Spawn async rendering
-        spawn_local(async {
-
+        spawn_local(async move {
             match init_wgpu(canvas).await {
                 Ok((device, surface, queue, config)) => {
-                    render_loop(device, surface, queue, config).await;
+                    render_loop(device, surface, queue, config, render_canvas, hud_handle).await;
                 }
 
                 Err(_) => {}
@@ -196,8 +520,7 @@ pub fn Canvas() -> impl IntoView {
     view! {
         <canvas
             node_ref=canvas_ref
-            width="100%"
-            height="100%"
+            style="width: 100%; height: 100%; display: block;"
         ></canvas>
     }
 }
\ No newline at end of file
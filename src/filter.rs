@@ -0,0 +1,430 @@
+use wgpu::util::DeviceExt;
+
+const CRT_SHADER: &str = include_str!("shaders/crt.wgsl");
+const COLOR_GRADE_SHADER: &str = include_str!("shaders/color_grade.wgsl");
+const BLOOM_SHADER: &str = include_str!("shaders/bloom.wgsl");
+
+/// The default chain applied when the caller doesn't supply its own preset.
+pub const DEFAULT_PRESET: &str = r#"{"passes":[{"shader":"crt"},{"shader":"color_grade"}]}"#;
+
+fn shader_source(name: &str) -> Option<&'static str> {
+    match name {
+        "crt" => Some(CRT_SHADER),
+        "color_grade" => Some(COLOR_GRADE_SHADER),
+        "bloom" => Some(BLOOM_SHADER),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub enum FilterChainError {
+    Parse(serde_json::Error),
+    UnknownShader(String),
+    EmptyChain,
+}
+
+impl std::fmt::Display for FilterChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterChainError::Parse(err) => write!(f, "invalid filter preset: {err}"),
+            FilterChainError::UnknownShader(name) => write!(f, "unknown filter shader: {name}"),
+            FilterChainError::EmptyChain => write!(f, "filter preset has no passes"),
+        }
+    }
+}
+
+impl std::error::Error for FilterChainError {}
+
+impl From<serde_json::Error> for FilterChainError {
+    fn from(err: serde_json::Error) -> Self {
+        FilterChainError::Parse(err)
+    }
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+#[derive(serde::Deserialize)]
+struct PassPreset {
+    shader: String,
+    #[serde(default = "default_scale")]
+    scale: f32,
+}
+
+#[derive(serde::Deserialize)]
+struct ChainPreset {
+    passes: Vec<PassPreset>,
+}
+
+/// Per-pass uniform block threaded into every filter shader.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct FilterUniforms {
+    source_size: [f32; 2],
+    output_size: [f32; 2],
+    frame_count: u32,
+    _padding: [u32; 3],
+}
+
+/// One stage of the post-processing chain: a full-screen fragment shader
+/// sampling the previous pass's output texture.
+struct FilterPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform_buffer: wgpu::Buffer,
+    /// Resolution this pass renders at, relative to the base render
+    /// resolution -- lets a pass run at reduced resolution and upscale.
+    scale: f32,
+    /// This pass's own render target, sized by `scale` so a reduced-scale
+    /// pass genuinely renders fewer pixels; the next pass then samples it
+    /// back up through the bilinear sampler. `None` for the chain's last
+    /// pass, which renders straight into the caller's `output` view instead.
+    output: Option<(wgpu::Texture, wgpu::TextureView)>,
+    output_width: u32,
+    output_height: u32,
+    /// Cached bind group for this pass's current source view. Rebuilt only
+    /// when invalidated (currently: whenever `resize_output` runs, since
+    /// that's the only thing that ever changes a pass's source texture) --
+    /// not reallocated every frame.
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+impl FilterPass {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat, shader_source: &str, scale: f32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Filter Pass Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Filter Pass Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Filter Pass Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Filter Pass Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Filter Pass Uniform Buffer"),
+            contents: bytemuck::bytes_of(&FilterUniforms {
+                source_size: [0.0, 0.0],
+                output_size: [0.0, 0.0],
+                frame_count: 0,
+                _padding: [0; 3],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            uniform_buffer,
+            scale,
+            output: None,
+            output_width: 0,
+            output_height: 0,
+            bind_group: None,
+        }
+    }
+
+    /// (Re)allocates this pass's render target for a chain sized
+    /// `base_width` x `base_height`. The last pass in the chain renders
+    /// straight into the caller's `output` view, so it gets no target of
+    /// its own.
+    fn resize_output(
+        &mut self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        base_width: u32,
+        base_height: u32,
+        is_last: bool,
+    ) {
+        // Either branch below changes this pass's source texture (its own
+        // output, or -- for the last pass -- the upstream pass's newly
+        // resized output), so the cached bind group is stale either way.
+        self.bind_group = None;
+
+        if is_last {
+            self.output = None;
+            self.output_width = base_width;
+            self.output_height = base_height;
+            return;
+        }
+
+        let width = ((base_width as f32 * self.scale) as u32).max(1);
+        let height = ((base_height as f32 * self.scale) as u32).max(1);
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Filter Pass Output"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        self.output = Some((texture, view));
+        self.output_width = width;
+        self.output_height = height;
+    }
+
+    /// Returns this pass's bind group for `source`, building and caching it
+    /// the first time it's needed after construction or a resize.
+    fn bind_group(&mut self, device: &wgpu::Device, source: &wgpu::TextureView) -> &wgpu::BindGroup {
+        if self.bind_group.is_none() {
+            self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Filter Pass Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.uniform_buffer.as_entire_binding(),
+                    },
+                ],
+            }));
+        }
+
+        self.bind_group.as_ref().unwrap()
+    }
+}
+
+/// An ordered sequence of full-screen post-processing passes, loaded from a
+/// JSON preset rather than hardcoded. Each pass samples the previous pass's
+/// output; every pass but the last renders into its own scratch texture
+/// (sized by that pass's `scale`, so a reduced-scale pass really does
+/// render fewer pixels), and the final pass renders straight into the
+/// caller's `output` view (typically the swapchain).
+pub struct FilterChain {
+    passes: Vec<FilterPass>,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    frame_count: u32,
+}
+
+impl FilterChain {
+    pub fn from_preset(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        preset: &str,
+    ) -> Result<Self, FilterChainError> {
+        let preset: ChainPreset = serde_json::from_str(preset)?;
+
+        if preset.passes.is_empty() {
+            return Err(FilterChainError::EmptyChain);
+        }
+
+        let last_index = preset.passes.len() - 1;
+        let passes = preset
+            .passes
+            .into_iter()
+            .enumerate()
+            .map(|(i, pass)| {
+                let source = shader_source(&pass.shader)
+                    .ok_or_else(|| FilterChainError::UnknownShader(pass.shader.clone()))?;
+                let mut pass = FilterPass::new(device, format, source, pass.scale);
+                pass.resize_output(device, format, width, height, i == last_index);
+                Ok(pass)
+            })
+            .collect::<Result<Vec<_>, FilterChainError>>()?;
+
+        Ok(Self {
+            passes,
+            format,
+            width,
+            height,
+            frame_count: 0,
+        })
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        let last_index = self.passes.len() - 1;
+        for (i, pass) in self.passes.iter_mut().enumerate() {
+            pass.resize_output(device, self.format, width, height, i == last_index);
+        }
+    }
+
+    /// Runs every configured pass over `source`, writing the final result
+    /// into `output`.
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+    ) {
+        self.frame_count += 1;
+        let frame_count = self.frame_count;
+
+        // `from_preset` rejects empty presets, so there's always at least
+        // one pass to run.
+        let (last, rest) = self
+            .passes
+            .split_last_mut()
+            .expect("FilterChain is constructed with at least one pass");
+
+        // Each pass's output is a `wgpu::TextureView`, a cheap Arc-backed
+        // handle -- cloning it to carry across loop iterations is far
+        // cheaper than the GPU allocations `run_pass` is trying to avoid,
+        // and sidesteps borrowing `current_input` from the same pass we
+        // need to mutate for its bind-group cache.
+        let mut current_input = source.clone();
+        let mut input_width = self.width;
+        let mut input_height = self.height;
+
+        for pass in rest.iter_mut() {
+            let target = pass
+                .output
+                .as_ref()
+                .map(|(_, view)| view.clone())
+                .expect("non-last pass always has its own output target");
+            run_pass(device, queue, encoder, frame_count, pass, &current_input, input_width, input_height, &target);
+            input_width = pass.output_width;
+            input_height = pass.output_height;
+            current_input = target;
+        }
+
+        run_pass(device, queue, encoder, frame_count, last, &current_input, input_width, input_height, output);
+    }
+}
+
+fn run_pass(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    encoder: &mut wgpu::CommandEncoder,
+    frame_count: u32,
+    pass: &mut FilterPass,
+    source: &wgpu::TextureView,
+    source_width: u32,
+    source_height: u32,
+    target: &wgpu::TextureView,
+) {
+    queue.write_buffer(
+        &pass.uniform_buffer,
+        0,
+        bytemuck::bytes_of(&FilterUniforms {
+            source_size: [source_width as f32, source_height as f32],
+            output_size: [pass.output_width as f32, pass.output_height as f32],
+            frame_count,
+            _padding: [0; 3],
+        }),
+    );
+
+    let bind_group = pass.bind_group(device, source);
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Filter Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(&pass.pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+}
@@ -0,0 +1,171 @@
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+
+use glyphon::{
+    Attrs, Buffer, Cache, Color, Family, FontSystem, Metrics, Resolution, Shaping, SwashCache,
+    TextArea, TextAtlas, TextBounds, TextRenderer, Viewport,
+};
+
+/// Embedded HUD font -- `fontdb`'s system-font scan is a no-op on `wasm32`,
+/// so the font has to ship with the binary instead.
+const HUD_FONT_BYTES: &[u8] = include_bytes!("assets/font.ttf");
+const HUD_FONT_FAMILY: &str = "DejaVu Sans";
+
+/// One piece of text queued for the HUD overlay: its content, screen-space
+/// position in physical pixels, color, and point size.
+#[derive(Clone, Debug)]
+pub struct TextSection {
+    pub text: String,
+    pub position: [f32; 2],
+    pub color: [u8; 4],
+    pub scale: f32,
+}
+
+impl TextSection {
+    pub fn new(text: impl Into<String>, position: [f32; 2]) -> Self {
+        Self {
+            text: text.into(),
+            position,
+            color: [255, 255, 255, 255],
+            scale: 16.0,
+        }
+    }
+
+    pub fn with_color(mut self, color: [u8; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// Cloneable handle for queuing [`TextSection`]s to draw over the scene.
+/// The render loop keeps its own private `HudHandle` for the FPS readout
+/// and merges it with whatever a caller holding a `provide_context`-shared
+/// handle has queued -- it never clears sections it doesn't own, so text
+/// pushed from outside the render closure survives across frames until its
+/// owner clears or replaces it.
+#[derive(Clone, Default)]
+pub struct HudHandle {
+    sections: Rc<RefCell<Vec<TextSection>>>,
+}
+
+impl HudHandle {
+    /// Queues `section` to be drawn on the next frame.
+    pub fn push(&self, section: TextSection) {
+        self.sections.borrow_mut().push(section);
+    }
+
+    /// Drops everything queued on this handle so far.
+    pub fn clear(&self) {
+        self.sections.borrow_mut().clear();
+    }
+
+    pub(crate) fn sections(&self) -> Ref<'_, Vec<TextSection>> {
+        self.sections.borrow()
+    }
+}
+
+/// Rasterizes queued [`TextSection`]s into textured quads and draws them over
+/// the scene in a second, alpha-blended render pass. Wraps `glyphon`'s
+/// font-system/atlas/renderer trio -- which manages its own glyph atlas and
+/// always renders with alpha blending, unlike the scene pipeline's
+/// `BlendState::REPLACE` -- behind the small API the render loop needs.
+pub struct HudOverlay {
+    font_system: FontSystem,
+    swash_cache: SwashCache,
+    viewport: Viewport,
+    atlas: TextAtlas,
+    renderer: TextRenderer,
+    buffers: Vec<Buffer>,
+}
+
+impl HudOverlay {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+        let cache = Cache::new(device);
+        let viewport = Viewport::new(device, &cache);
+        let mut atlas = TextAtlas::new(device, queue, &cache, format);
+        let renderer = TextRenderer::new(&mut atlas, device, wgpu::MultisampleState::default(), None);
+
+        // `FontSystem::new()` scans the system for installed fonts via
+        // `fontdb`, which is a no-op on `wasm32` (no filesystem to scan) --
+        // so the HUD font has to be embedded and loaded explicitly instead,
+        // the same way `Texture::from_bytes` embeds the placeholder image.
+        let mut font_system = FontSystem::new();
+        font_system.db_mut().load_font_data(HUD_FONT_BYTES.to_vec());
+
+        Self {
+            font_system,
+            swash_cache: SwashCache::new(),
+            viewport,
+            atlas,
+            renderer,
+            buffers: Vec::new(),
+        }
+    }
+
+    /// Shapes `sections` and uploads any new glyphs to the atlas, replacing
+    /// whatever was prepared for the previous frame.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        sections: &[TextSection],
+    ) {
+        self.viewport.update(queue, Resolution { width, height });
+
+        self.buffers.clear();
+        self.buffers.reserve(sections.len());
+        for section in sections {
+            let mut buffer = Buffer::new(&mut self.font_system, Metrics::new(section.scale, section.scale * 1.2));
+            buffer.set_text(
+                &mut self.font_system,
+                &section.text,
+                Attrs::new().family(Family::Name(HUD_FONT_FAMILY)),
+                Shaping::Advanced,
+            );
+            buffer.shape_until_scroll(&mut self.font_system, false);
+            self.buffers.push(buffer);
+        }
+
+        let text_areas = self.buffers.iter().zip(sections).map(|(buffer, section)| TextArea {
+            buffer,
+            left: section.position[0],
+            top: section.position[1],
+            scale: 1.0,
+            bounds: TextBounds {
+                left: 0,
+                top: 0,
+                right: width as i32,
+                bottom: height as i32,
+            },
+            default_color: Color::rgba(section.color[0], section.color[1], section.color[2], section.color[3]),
+            custom_glyphs: &[],
+        });
+
+        self.renderer
+            .prepare(
+                device,
+                queue,
+                &mut self.font_system,
+                &mut self.atlas,
+                &self.viewport,
+                text_areas,
+                &mut self.swash_cache,
+            )
+            .expect("failed to prepare HUD text");
+    }
+
+    /// Draws the text prepared by the last [`HudOverlay::prepare`] call into
+    /// `render_pass`, alpha-blended over whatever is already there.
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        self.renderer
+            .render(&self.atlas, &self.viewport, render_pass)
+            .expect("failed to render HUD text");
+    }
+}
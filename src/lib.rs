@@ -0,0 +1,5 @@
+pub mod canvas;
+pub mod filter;
+pub mod hud;
+pub mod texture;
+pub mod vertex;
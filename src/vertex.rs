@@ -0,0 +1,16 @@
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub tex_coords: [f32; 2],
+}
+
+pub const VERTICES: &[Vertex] = &[
+    Vertex { position: [-0.5, 0.5, 0.0], color: [1.0, 0.0, 0.0], tex_coords: [0.0, 0.0] },
+    Vertex { position: [0.5, 0.5, 0.0], color: [0.0, 1.0, 0.0], tex_coords: [1.0, 0.0] },
+    Vertex { position: [0.5, -0.5, 0.0], color: [0.0, 0.0, 1.0], tex_coords: [1.0, 1.0] },
+    Vertex { position: [-0.5, -0.5, 0.0], color: [1.0, 1.0, 0.0], tex_coords: [0.0, 1.0] },
+];
+
+pub const INDICES: &[u16] = &[0, 2, 1, 0, 3, 2];